@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+/// Un proveedor alternativo (por ejemplo, un endpoint compatible con OpenAI)
+/// al que se le puede apuntar el transcriptor en lugar del proveedor por defecto.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Provider {
+    pub name: String,
+    pub base_url: String,
+}
+
+/// Configuracion persistida de la app: proxy y valores por defecto que se
+/// aplican a cada transcripcion salvo que la llamada puntual los
+/// sobreescriba. La clave API nunca vive aca: se guarda cifrada via
+/// `secrets::save_api_key`, para que `config.toml` no sea una segunda copia
+/// en claro de la credencial.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Config {
+    pub proxy: Option<String>,
+    pub default_model: Option<String>,
+    pub default_language: Option<String>,
+    pub interpreter_path: Option<String>,
+    pub active_provider: Option<String>,
+    #[serde(default)]
+    pub providers: Vec<Provider>,
+}
+
+impl Config {
+    /// El proveedor activo, si hay uno seleccionado y existe en la lista.
+    pub fn active_provider(&self) -> Option<&Provider> {
+        let name = self.active_provider.as_deref()?;
+        self.providers.iter().find(|p| p.name == name)
+    }
+}
+
+fn config_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("No se pudo resolver el directorio de configuracion: {}", e))?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir.join("config.toml"))
+}
+
+/// Carga la configuracion desde `config.toml` en el directorio de config de la
+/// app. Si el archivo todavia no existe, devuelve la configuracion por defecto.
+pub fn load_config(app: &AppHandle) -> Result<Config, String> {
+    let path = config_path(app)?;
+    if !path.is_file() {
+        return Ok(Config::default());
+    }
+
+    let raw = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Error leyendo config.toml: {}", e))?;
+    toml::from_str(&raw).map_err(|e| format!("Error parseando config.toml: {}", e))
+}
+
+/// Guarda la configuracion en `config.toml`, sobreescribiendo lo que hubiera.
+pub fn save_config(app: &AppHandle, config: &Config) -> Result<(), String> {
+    let path = config_path(app)?;
+    let raw = toml::to_string_pretty(config)
+        .map_err(|e| format!("Error serializando config: {}", e))?;
+    std::fs::write(&path, raw).map_err(|e| format!("Error escribiendo config.toml: {}", e))
+}
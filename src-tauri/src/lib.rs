@@ -1,16 +1,81 @@
-use std::process::Command;
+mod config;
+mod python_runtime;
+mod secrets;
+
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
 use serde::{Deserialize, Serialize};
+use tauri::ipc::Channel;
+use tauri::State;
+use tauri_plugin_shell::{process::CommandEvent, ShellExt};
+
+use python_runtime::{resolve_cli_path, resolve_python_runtime, PythonRuntime};
+
+/// Proceso del motor de transcripcion ya en marcha: un interprete de Python
+/// lanzado directamente, o el sidecar empaquetado con PyInstaller.
+///
+/// El lado `Sidecar` guarda un `Option` porque `CommandChild::kill` consume el
+/// valor; `kill()` lo saca con `take()` y deja `None` en su lugar, sin tener
+/// que sacar el job entero del `JobRegistry` para matarlo.
+enum EngineChild {
+    Process(Child),
+    Sidecar(Option<tauri_plugin_shell::process::CommandChild>),
+}
+
+impl EngineChild {
+    fn kill(&mut self) -> Result<(), String> {
+        match self {
+            EngineChild::Process(child) => child.kill().map_err(|e| e.to_string()),
+            EngineChild::Sidecar(child) => match child.take() {
+                Some(child) => child.kill().map_err(|e| e.to_string()),
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+/// Job de transcripcion en curso, guardado en el estado administrado por Tauri
+/// para poder cancelarlo desde otro comando mientras `transcribe_audio` sigue corriendo.
+struct TranscriptionJob {
+    child: EngineChild,
+}
+
+/// Registro de jobs de transcripcion activos, indexados por `job_id`.
+type JobRegistry = Mutex<HashMap<String, TranscriptionJob>>;
+
+/// `job_id`s cancelados por el usuario, para distinguir esa salida de un crash
+/// del motor una vez que el job ya no esta en el `JobRegistry`.
+type CancelledJobs = Mutex<HashSet<String>>;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct TranscribeConfig {
+    file_path: String,
+    // Opcional: si no viene, se usa la clave ya desbloqueada en `UnlockedSecrets`.
+    api_key: Option<String>,
+    language: Option<String>,
+    model: Option<String>,
+    prompt: Option<String>,
+    interpreter_path: Option<String>,
+}
+
+/// Lo que efectivamente se serializa y se le pasa al motor de Python: los
+/// campos de `TranscribeConfig` de esta llamada puntual, ya mezclados con los
+/// valores por defecto de `config::Config` (proxy, modelo, endpoint del
+/// proveedor activo, etc.).
+#[derive(Debug, Serialize)]
+struct EngineConfig {
     file_path: String,
     api_key: String,
     language: Option<String>,
     model: Option<String>,
     prompt: Option<String>,
+    proxy: Option<String>,
+    base_url: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct TranscribeResult {
     success: bool,
     text: Option<String>,
@@ -19,46 +84,208 @@ struct TranscribeResult {
     chunks: Option<i32>,
 }
 
+/// Diagnostico de `test_api_key`: distingue una clave invalida (401) de un
+/// problema de conectividad (timeout, proxy) o de un motor Python ausente.
+#[derive(Debug, Serialize, Deserialize)]
+struct ApiKeyCheck {
+    valid: bool,
+    error: Option<String>,
+    detail: Option<String>,
+    // Modelo/endpoint que el CLI de Python efectivamente probo, para que la
+    // pantalla de ajustes pueda mostrar contra que se valido la clave.
+    tested_endpoint: Option<String>,
+}
+
+/// Eventos que el motor de Python va emitiendo linea a linea mientras transcribe.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ProgressEvent {
+    Progress {
+        chunk: i32,
+        total: i32,
+        partial_text: String,
+    },
+    Result(TranscribeResult),
+    Error {
+        message: String,
+    },
+}
+
 #[tauri::command]
-async fn transcribe_audio(config: TranscribeConfig) -> Result<TranscribeResult, String> {
-    // Construir path al CLI de Python (por ahora solo modo desarrollo)
-    let python_cli = std::env::current_dir()
-        .map_err(|e| e.to_string())?
-        .join("python-engine")
-        .join("cli.py");
+async fn transcribe_audio(
+    app: tauri::AppHandle,
+    job_id: String,
+    config: TranscribeConfig,
+    on_progress: Channel<ProgressEvent>,
+    jobs: State<'_, JobRegistry>,
+    cancelled_jobs: State<'_, CancelledJobs>,
+    unlocked_secrets: State<'_, secrets::UnlockedSecrets>,
+) -> Result<TranscribeResult, String> {
+    // Mezclar los defaults persistidos con los campos de esta llamada puntual.
+    let defaults = config::load_config(&app).unwrap_or_default();
+    let interpreter_path = config
+        .interpreter_path
+        .clone()
+        .or_else(|| defaults.interpreter_path.clone());
+    let runtime = resolve_python_runtime(&app, interpreter_path.as_deref())?;
+    let cli_path = resolve_cli_path(&app)?;
+
+    let api_key = config
+        .api_key
+        .clone()
+        .or_else(|| unlocked_secrets.lock().unwrap().clone())
+        .ok_or_else(|| "No hay clave API: pasala en la llamada o desbloquea las credenciales guardadas".to_string())?;
+
+    let engine_config = EngineConfig {
+        file_path: config.file_path.clone(),
+        api_key,
+        language: config.language.clone().or(defaults.default_language.clone()),
+        model: config.model.clone().or(defaults.default_model.clone()),
+        prompt: config.prompt.clone(),
+        proxy: defaults.proxy.clone(),
+        base_url: defaults.active_provider().map(|p| p.base_url.clone()),
+    };
 
     // Preparar JSON de configuraci√≥n
-    let config_json = serde_json::to_string(&config)
+    let config_json = serde_json::to_string(&engine_config)
         .map_err(|e| format!("Error serializando config: {}", e))?;
 
-    // Ejecutar Python subprocess
-    let output = Command::new("python3")
-        .arg(python_cli)
-        .arg("transcribe")
-        .stdin(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .spawn()
-        .and_then(|mut child| {
-            use std::io::Write;
-            if let Some(mut stdin) = child.stdin.take() {
-                stdin.write_all(config_json.as_bytes())?;
+    let mut final_result: Option<TranscribeResult> = None;
+    let mut stderr_tail = String::new();
+
+    match runtime {
+        PythonRuntime::Interpreter(interpreter) => {
+            // Ejecutar Python subprocess, manteniendo stdout abierto para leerlo linea a linea
+            let mut child = Command::new(interpreter)
+                .arg(cli_path)
+                .arg("transcribe")
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .map_err(|e| format!("Error ejecutando Python: {}", e))?;
+
+            {
+                use std::io::Write;
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin
+                        .write_all(config_json.as_bytes())
+                        .map_err(|e| format!("Error escribiendo en stdin: {}", e))?;
+                }
             }
-            child.wait_with_output()
-        })
-        .map_err(|e| format!("Error ejecutando Python: {}", e))?;
 
-    // Parsear resultado
-    if output.status.success() {
-        let result_str = String::from_utf8_lossy(&output.stdout);
-        serde_json::from_str::<TranscribeResult>(&result_str)
-            .map_err(|e| format!("Error parseando resultado: {}", e))
+            let stdout = child
+                .stdout
+                .take()
+                .ok_or_else(|| "No se pudo capturar stdout del proceso Python".to_string())?;
+
+            // Registrar el job para que `cancel_transcription` pueda encontrarlo y matarlo.
+            jobs.lock().unwrap().insert(
+                job_id.clone(),
+                TranscriptionJob { child: EngineChild::Process(child) },
+            );
+
+            for line in BufReader::new(stdout).lines() {
+                let line = line.map_err(|e| format!("Error leyendo stdout: {}", e))?;
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+
+                // El stdout del CLI puede traer ruido no-JSON (logs, warnings de librerias);
+                // lo ignoramos en vez de abortar la transcripcion.
+                let event = match serde_json::from_str::<ProgressEvent>(line) {
+                    Ok(event) => event,
+                    Err(_) => continue,
+                };
+
+                if let ProgressEvent::Result(ref result) = event {
+                    final_result = Some(result.clone());
+                }
+
+                let _ = on_progress.send(event);
+            }
+
+            if let Some(TranscriptionJob { child: EngineChild::Process(child) }) =
+                jobs.lock().unwrap().remove(&job_id)
+            {
+                let output = child
+                    .wait_with_output()
+                    .map_err(|e| format!("Error esperando a Python: {}", e))?;
+                stderr_tail = String::from_utf8_lossy(&output.stderr).to_string();
+            }
+        }
+        PythonRuntime::Sidecar => {
+            let (mut rx, mut child) = app
+                .shell()
+                .sidecar("python-engine")
+                .map_err(|e| format!("Error preparando el sidecar de Python: {}", e))?
+                .args(["transcribe"])
+                .spawn()
+                .map_err(|e| format!("Error ejecutando el sidecar de Python: {}", e))?;
+
+            child
+                .write(config_json.as_bytes())
+                .map_err(|e| format!("Error escribiendo en stdin: {}", e))?;
+
+            jobs.lock().unwrap().insert(
+                job_id.clone(),
+                TranscriptionJob { child: EngineChild::Sidecar(Some(child)) },
+            );
+
+            while let Some(event) = rx.recv().await {
+                match event {
+                    CommandEvent::Stdout(bytes) => {
+                        let line = String::from_utf8_lossy(&bytes);
+                        let line = line.trim();
+                        if line.is_empty() {
+                            continue;
+                        }
+                        let event = match serde_json::from_str::<ProgressEvent>(line) {
+                            Ok(event) => event,
+                            Err(_) => continue,
+                        };
+                        if let ProgressEvent::Result(ref result) = event {
+                            final_result = Some(result.clone());
+                        }
+                        let _ = on_progress.send(event);
+                    }
+                    CommandEvent::Stderr(bytes) => {
+                        stderr_tail = String::from_utf8_lossy(&bytes).to_string();
+                    }
+                    CommandEvent::Error(err) => stderr_tail = err,
+                    CommandEvent::Terminated(_) => break,
+                    _ => {}
+                }
+            }
+
+            jobs.lock().unwrap().remove(&job_id);
+        }
+    }
+
+    let was_cancelled = cancelled_jobs.lock().unwrap().remove(&job_id);
+    if was_cancelled {
+        return Ok(TranscribeResult {
+            success: false,
+            text: None,
+            error: Some("cancelled".to_string()),
+            duration: None,
+            chunks: None,
+        });
+    }
+
+    if let Some(result) = final_result {
+        return Ok(result);
+    }
+
+    // El proceso no emitio una linea "result" (p. ej. crasheo antes de terminar).
+    if stderr_tail.is_empty() {
+        Err("El proceso Python termino sin reportar un resultado".to_string())
     } else {
-        let error_str = String::from_utf8_lossy(&output.stderr);
         Ok(TranscribeResult {
             success: false,
             text: None,
-            error: Some(error_str.to_string()),
+            error: Some(stderr_tail),
             duration: None,
             chunks: None,
         })
@@ -66,20 +293,135 @@ async fn transcribe_audio(config: TranscribeConfig) -> Result<TranscribeResult,
 }
 
 #[tauri::command]
-async fn test_api_key(api_key: String) -> Result<bool, String> {
-    let python_cli = std::env::current_dir()
-        .map_err(|e| e.to_string())?
-        .join("python-engine")
-        .join("cli.py");
+fn cancel_transcription(
+    job_id: String,
+    jobs: State<'_, JobRegistry>,
+    cancelled_jobs: State<'_, CancelledJobs>,
+) -> Result<bool, String> {
+    // Importante: no saca el job del registro, solo lo mata in-place.
+    // `transcribe_audio` es quien lo saca y hace `wait_with_output()` una vez
+    // que su loop de lectura termina, para no dejar un proceso zombie.
+    //
+    // Y solo marcamos `cancelled_jobs` si de verdad encontramos y matamos el
+    // job: si todavia no esta en el registro (p. ej. cancelaron justo despues
+    // de lanzarlo, mientras `transcribe_audio` seguia resolviendo el interprete)
+    // o si el `job_id` no existe, no queremos dejar una entrada huerfana que
+    // luego le robe el resultado real a una transcripcion que nunca se cancelo.
+    let mut jobs = jobs.lock().unwrap();
+    match jobs.get_mut(&job_id) {
+        Some(job) => {
+            job.child.kill().map_err(|e| format!("Error matando el proceso: {}", e))?;
+            cancelled_jobs.lock().unwrap().insert(job_id);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+#[tauri::command]
+async fn test_api_key(
+    app: tauri::AppHandle,
+    api_key: Option<String>,
+    unlocked_secrets: State<'_, secrets::UnlockedSecrets>,
+) -> Result<ApiKeyCheck, String> {
+    let api_key = api_key
+        .or_else(|| unlocked_secrets.lock().unwrap().clone())
+        .ok_or_else(|| "No hay clave API: pasala en la llamada o desbloquea las credenciales guardadas".to_string())?;
+
+    let runtime = resolve_python_runtime(&app, None)?;
+    let cli_path = resolve_cli_path(&app)?;
+
+    // La clave API va por stdin, nunca como argumento de linea de comandos:
+    // un argv queda visible para cualquier otro proceso local via `ps` o
+    // `/proc/<pid>/cmdline`, lo que tiraria abajo el cifrado en reposo que
+    // agrega `secrets.rs`.
+    let (stdout, stderr) = match runtime {
+        PythonRuntime::Interpreter(interpreter) => {
+            let mut child = Command::new(interpreter)
+                .arg(cli_path)
+                .arg("test_api")
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .map_err(|e| format!("Error ejecutando Python: {}", e))?;
+
+            {
+                use std::io::Write;
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin
+                        .write_all(api_key.as_bytes())
+                        .map_err(|e| format!("Error escribiendo en stdin: {}", e))?;
+                }
+            }
+
+            let output = child
+                .wait_with_output()
+                .map_err(|e| format!("Error esperando a Python: {}", e))?;
+            (output.stdout, output.stderr)
+        }
+        PythonRuntime::Sidecar => {
+            let (mut rx, mut child) = app
+                .shell()
+                .sidecar("python-engine")
+                .map_err(|e| format!("Error preparando el sidecar de Python: {}", e))?
+                .args(["test_api"])
+                .spawn()
+                .map_err(|e| format!("Error ejecutando el sidecar de Python: {}", e))?;
+
+            child
+                .write(api_key.as_bytes())
+                .map_err(|e| format!("Error escribiendo en stdin: {}", e))?;
+
+            let mut stdout_buf = Vec::new();
+            let mut stderr_buf = Vec::new();
+            while let Some(event) = rx.recv().await {
+                match event {
+                    CommandEvent::Stdout(bytes) => stdout_buf.extend_from_slice(&bytes),
+                    CommandEvent::Stderr(bytes) => stderr_buf.extend_from_slice(&bytes),
+                    CommandEvent::Error(err) => stderr_buf.extend_from_slice(err.as_bytes()),
+                    CommandEvent::Terminated(_) => break,
+                    _ => {}
+                }
+            }
+            (stdout_buf, stderr_buf)
+        }
+    };
+
+    // Camino feliz: el CLI de Python reporta un diagnostico estructurado por stdout.
+    if let Ok(check) = serde_json::from_slice::<ApiKeyCheck>(&stdout) {
+        return Ok(check);
+    }
+
+    // El proceso termino sin ese JSON (crash antes de poder reportar); hacemos
+    // lo mejor posible con lo que haya quedado en stderr.
+    let detail = String::from_utf8_lossy(&stderr).trim().to_string();
+    Ok(ApiKeyCheck {
+        valid: false,
+        error: Some("No se pudo validar la clave API".to_string()),
+        detail: if detail.is_empty() { None } else { Some(detail) },
+        tested_endpoint: None,
+    })
+}
+
+#[tauri::command]
+fn load_config(app: tauri::AppHandle) -> Result<config::Config, String> {
+    config::load_config(&app)
+}
 
-    let output = Command::new("python3")
-        .arg(python_cli)
-        .arg("test_api")
-        .arg(&api_key)
-        .output()
-        .map_err(|e| format!("Error ejecutando Python: {}", e))?;
+#[tauri::command]
+fn save_config(app: tauri::AppHandle, new_config: config::Config) -> Result<(), String> {
+    config::save_config(&app, &new_config)
+}
 
-    Ok(output.status.success())
+#[tauri::command]
+fn save_api_key(app: tauri::AppHandle, master_password: String, api_key: String) -> Result<(), String> {
+    secrets::save_api_key(&app, &master_password, &api_key)
+}
+
+#[tauri::command]
+fn unlock(app: tauri::AppHandle, master_password: String) -> Result<(), String> {
+    secrets::unlock(&app, &master_password)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -87,9 +429,17 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_clipboard_manager::init())
+        .manage(JobRegistry::default())
+        .manage(CancelledJobs::default())
+        .manage(secrets::UnlockedSecrets::default())
         .invoke_handler(tauri::generate_handler![
             transcribe_audio,
-            test_api_key
+            cancel_transcription,
+            test_api_key,
+            load_config,
+            save_config,
+            save_api_key,
+            unlock
         ])
         .setup(|app| {
             if cfg!(debug_assertions) {
@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use tauri::{AppHandle, Manager};
+
+/// Como se va a invocar el motor de transcripcion: un interprete de Python
+/// resuelto en el sistema, o el ejecutable empaquetado con PyInstaller que
+/// viaja como sidecar de Tauri en los builds de produccion.
+#[derive(Debug, Clone)]
+pub enum PythonRuntime {
+    Interpreter(PathBuf),
+    Sidecar,
+}
+
+/// Resuelve que interprete usar, en orden de prioridad:
+/// 1. Un `interpreter_path` explicito (configuracion del usuario).
+/// 2. `python3`/`python` encontrados en PATH via el crate `which`.
+/// 3. El sidecar empaquetado con PyInstaller, si la app esta empaquetada.
+pub fn resolve_python_runtime(
+    app: &AppHandle,
+    interpreter_path: Option<&str>,
+) -> Result<PythonRuntime, String> {
+    if let Some(path) = interpreter_path {
+        let path = PathBuf::from(path);
+        return if path.is_file() {
+            Ok(PythonRuntime::Interpreter(path))
+        } else {
+            Err(format!(
+                "El interprete configurado no existe: {}",
+                path.display()
+            ))
+        };
+    }
+
+    for name in ["python3", "python"] {
+        if let Ok(path) = which::which(name) {
+            return Ok(PythonRuntime::Interpreter(path));
+        }
+    }
+
+    // Sin interprete en PATH: en una app empaquetada caemos al sidecar de PyInstaller.
+    // `resource_dir()` resuelve igual de bien en `tauri dev`, donde el sidecar
+    // nunca fue compilado, asi que no alcanza como senal de "estamos empaquetados";
+    // usamos el mismo `cfg!(debug_assertions)` que ya distingue dev/prod en `run()`.
+    if !cfg!(debug_assertions) && app.path().resource_dir().is_ok() {
+        return Ok(PythonRuntime::Sidecar);
+    }
+
+    Err("Python interpreter not found; set interpreter_path in settings".to_string())
+}
+
+/// Ruta al `cli.py` del motor de Python. En produccion se resuelve contra el
+/// directorio de recursos de la app empaquetada; en desarrollo (sin recursos
+/// empaquetados) cae de vuelta al directorio de trabajo del proyecto.
+pub fn resolve_cli_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if let Ok(resource_dir) = app.path().resource_dir() {
+        let bundled = resource_dir.join("python-engine").join("cli.py");
+        if bundled.is_file() {
+            return Ok(bundled);
+        }
+    }
+
+    std::env::current_dir()
+        .map_err(|e| e.to_string())
+        .map(|dir| dir.join("python-engine").join("cli.py"))
+}
@@ -0,0 +1,133 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+/// Clave API descifrada en memoria para la sesion actual, poblada por `unlock`.
+/// Vive unicamente en RAM: nunca se vuelve a escribir a disco en claro.
+pub type UnlockedSecrets = Mutex<Option<String>>;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct EncryptedSecret {
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn secrets_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("No se pudo resolver el directorio de datos de la app: {}", e))?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir.join("secrets.json"))
+}
+
+fn derive_key(master_password: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(master_password.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Error derivando la clave de cifrado: {}", e))?;
+    Ok(key)
+}
+
+/// Cifra `api_key` con una clave derivada de `master_password` via Argon2.
+/// Pura (sin tocar disco ni `AppHandle`) para poder probarla directamente.
+fn encrypt_secret(master_password: &str, api_key: &str) -> Result<EncryptedSecret, String> {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(master_password, &salt)?;
+
+    let mut nonce_bytes = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let cipher = ChaCha20Poly1305::new(key.as_slice().into());
+    let ciphertext = cipher
+        .encrypt(nonce, api_key.as_bytes())
+        .map_err(|e| format!("Error cifrando la clave API: {}", e))?;
+
+    Ok(EncryptedSecret {
+        salt: hex::encode(salt),
+        nonce: hex::encode(nonce_bytes),
+        ciphertext: hex::encode(ciphertext),
+    })
+}
+
+/// Descifra un `EncryptedSecret` con `master_password`. Devuelve el mismo
+/// mensaje generico ante una contrasena incorrecta que ante datos corruptos,
+/// para no filtrar nada sobre por que fallo.
+fn decrypt_secret(master_password: &str, encrypted: &EncryptedSecret) -> Result<String, String> {
+    let salt = hex::decode(&encrypted.salt).map_err(|_| "Contrasena maestra incorrecta".to_string())?;
+    let nonce_bytes =
+        hex::decode(&encrypted.nonce).map_err(|_| "Contrasena maestra incorrecta".to_string())?;
+    let ciphertext = hex::decode(&encrypted.ciphertext)
+        .map_err(|_| "Contrasena maestra incorrecta".to_string())?;
+
+    if nonce_bytes.len() != 12 {
+        return Err("Contrasena maestra incorrecta".to_string());
+    }
+
+    let key = derive_key(master_password, &salt)
+        .map_err(|_| "Contrasena maestra incorrecta".to_string())?;
+    let cipher = ChaCha20Poly1305::new(key.as_slice().into());
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let plaintext = cipher
+        .decrypt(nonce, ciphertext.as_ref())
+        .map_err(|_| "Contrasena maestra incorrecta".to_string())?;
+
+    String::from_utf8(plaintext).map_err(|e| format!("La clave descifrada no es UTF-8 valido: {}", e))
+}
+
+/// Cifra `api_key` con una clave derivada de `master_password` via Argon2, y
+/// persiste ciphertext + salt + nonce en `secrets.json` dentro del directorio
+/// de datos de la app. La clave nunca se escribe en claro a disco.
+pub fn save_api_key(app: &AppHandle, master_password: &str, api_key: &str) -> Result<(), String> {
+    let encrypted = encrypt_secret(master_password, api_key)?;
+    let raw = serde_json::to_string_pretty(&encrypted)
+        .map_err(|e| format!("Error serializando el secreto: {}", e))?;
+    std::fs::write(secrets_path(app)?, raw)
+        .map_err(|e| format!("Error escribiendo secrets.json: {}", e))
+}
+
+/// Descifra la clave API guardada usando `master_password` y la deja en
+/// `UnlockedSecrets` para el resto de la sesion, sin que el frontend tenga
+/// que volver a mandarla en cada invocacion.
+pub fn unlock(app: &AppHandle, master_password: &str) -> Result<(), String> {
+    let path = secrets_path(app)?;
+    let raw = std::fs::read_to_string(&path)
+        .map_err(|_| "No hay ninguna clave API guardada todavia".to_string())?;
+    let encrypted: EncryptedSecret =
+        serde_json::from_str(&raw).map_err(|e| format!("Error leyendo secrets.json: {}", e))?;
+
+    let api_key = decrypt_secret(master_password, &encrypted)?;
+
+    app.state::<UnlockedSecrets>().lock().unwrap().replace(api_key);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_with_the_right_master_password() {
+        let encrypted = encrypt_secret("hunter2", "sk-test-123").unwrap();
+        let decrypted = decrypt_secret("hunter2", &encrypted).unwrap();
+        assert_eq!(decrypted, "sk-test-123");
+    }
+
+    #[test]
+    fn rejects_the_wrong_master_password() {
+        let encrypted = encrypt_secret("hunter2", "sk-test-123").unwrap();
+        let err = decrypt_secret("not-hunter2", &encrypted).unwrap_err();
+        assert_eq!(err, "Contrasena maestra incorrecta");
+    }
+}